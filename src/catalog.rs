@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use metaflac::Tag;
+use walkdir::DirEntry;
+
+use crate::tag as vorbis;
+
+struct Track {
+    tracknumber: String,
+    title: String,
+    duration: String,
+}
+
+struct Album {
+    year: String,
+    genre: String,
+    tracks: Vec<Track>,
+}
+
+fn get(tag: &Tag, field: &str) -> String {
+    tag.vorbis_comments()
+        .and_then(|comments| comments.get(field))
+        .and_then(|values| values.first())
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn format_duration(total_samples: u64, sample_rate: u32) -> String {
+    if sample_rate == 0 {
+        return "--:--".to_string();
+    }
+    let total_secs = total_samples / sample_rate as u64;
+    format!("{}:{:02}", total_secs / 60, total_secs % 60)
+}
+
+/// Sort key for ordering tracks by TRACKNUMBER; tracks with a missing or
+/// non-numeric TRACKNUMBER sort last instead of panicking or grouping at 0.
+fn tracknumber_sort_key(tracknumber: &str) -> u32 {
+    tracknumber.parse().unwrap_or(u32::MAX)
+}
+
+/// Reads every FLAC entry's Vorbis comments and groups them by (artist,
+/// album); entries whose tags can't be read are skipped.
+pub fn collect(entries: &[DirEntry]) -> BTreeMap<(String, String), Album> {
+    let mut albums: BTreeMap<(String, String), Album> = BTreeMap::new();
+
+    for entry in entries {
+        let Ok(tag) = Tag::read_from_path(entry.path()) else {
+            continue;
+        };
+
+        let artist = get(&tag, vorbis::ARTIST);
+        let album_name = get(&tag, vorbis::ALBUM);
+        let duration = tag
+            .get_streaminfo()
+            .map(|info| format_duration(info.total_samples, info.sample_rate))
+            .unwrap_or_else(|| "--:--".to_string());
+
+        let album = albums
+            .entry((artist, album_name))
+            .or_insert_with(|| Album {
+                year: get(&tag, vorbis::DATE),
+                genre: get(&tag, vorbis::GENRE),
+                tracks: Vec::new(),
+            });
+
+        album.tracks.push(Track {
+            tracknumber: get(&tag, vorbis::TRACKNUMBER),
+            title: get(&tag, vorbis::TITLE),
+            duration,
+        });
+    }
+
+    for album in albums.values_mut() {
+        album
+            .tracks
+            .sort_by_key(|track| tracknumber_sort_key(&track.tracknumber));
+    }
+
+    albums
+}
+
+fn escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_replaces_html_special_characters() {
+        assert_eq!(
+            escape(r#"<script>alert("hi" & 'bye')</script>"#),
+            "&lt;script&gt;alert(&quot;hi&quot; &amp; &#39;bye&#39;)&lt;/script&gt;"
+        );
+    }
+
+    #[test]
+    fn escape_passes_through_plain_text() {
+        assert_eq!(escape("Bowie"), "Bowie");
+    }
+
+    #[test]
+    fn format_duration_pads_seconds_under_ten() {
+        assert_eq!(format_duration(5 * 44_100, 44_100), "0:05");
+    }
+
+    #[test]
+    fn format_duration_carries_minutes() {
+        assert_eq!(format_duration(125 * 44_100, 44_100), "2:05");
+    }
+
+    #[test]
+    fn format_duration_guards_zero_sample_rate() {
+        assert_eq!(format_duration(100, 0), "--:--");
+    }
+
+    #[test]
+    fn tracknumber_sort_key_parses_valid_numbers() {
+        assert_eq!(tracknumber_sort_key("7"), 7);
+    }
+
+    #[test]
+    fn tracknumber_sort_key_falls_back_to_max_for_invalid() {
+        assert_eq!(tracknumber_sort_key(""), u32::MAX);
+        assert_eq!(tracknumber_sort_key("five"), u32::MAX);
+    }
+}
+
+/// Renders a self-contained HTML index of `albums`, grouped by artist then
+/// album, with every tag-derived string HTML-escaped.
+pub fn render_html(albums: &BTreeMap<(String, String), Album>) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>audio-lint catalog</title>\n<style>\n");
+    html.push_str(
+        "body{font-family:sans-serif;margin:2rem}\nh2{margin-top:2rem}\n\
+         table{border-collapse:collapse;width:100%}\n\
+         td,th{text-align:left;padding:.25rem .5rem;border-bottom:1px solid #ddd}\n",
+    );
+    html.push_str("</style>\n</head>\n<body>\n<h1>audio-lint catalog</h1>\n");
+
+    for ((artist, album_name), album) in albums {
+        let _ = write!(
+            html,
+            "<h2>{} &mdash; {}</h2>\n<p>{} &middot; {}</p>\n\
+             <table>\n<tr><th>#</th><th>Title</th><th>Duration</th></tr>\n",
+            escape(artist),
+            escape(album_name),
+            escape(&album.year),
+            escape(&album.genre),
+        );
+        for track in &album.tracks {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&track.tracknumber),
+                escape(&track.title),
+                escape(&track.duration),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
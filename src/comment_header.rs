@@ -0,0 +1,189 @@
+//! The vendor-string-plus-`KEY=value`-list metadata block shared by Ogg
+//! Vorbis ("\x03vorbis") and Ogg Opus ("OpusTags") streams. Both codecs
+//! carry it as the second packet of the logical stream and lay it out
+//! identically; they differ only in the magic bytes in front, so
+//! `OggBackend` and `OpusBackend` in `tag.rs` both delegate here and just
+//! pass their own magic.
+
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ogg::reading::PacketReader;
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+struct RawPacket {
+    data: Vec<u8>,
+    stream_serial: u32,
+    absgp_page: u64,
+    last_in_page: bool,
+    last_in_stream: bool,
+}
+
+/// A parsed comment packet plus the raw packets surrounding it, so
+/// `write_to_path` can splice the edited comment packet back into an
+/// otherwise byte-identical stream.
+pub struct CommentHeader {
+    magic: &'static [u8],
+    vendor: String,
+    comments: Vec<(String, String)>,
+    trailing: Vec<u8>,
+    packets: Vec<RawPacket>,
+    comment_packet_index: usize,
+}
+
+impl CommentHeader {
+    pub fn read_from_path(path: &Path, magic: &'static [u8]) -> Result<Self> {
+        let file = File::open(path)?;
+        let mut reader = PacketReader::new(file);
+        let mut packets = Vec::new();
+
+        while let Some(packet) = reader.read_packet()? {
+            packets.push(RawPacket {
+                stream_serial: packet.stream_serial(),
+                absgp_page: packet.absgp_page(),
+                last_in_page: packet.last_in_page(),
+                last_in_stream: packet.last_packet(),
+                data: packet.data,
+            });
+        }
+
+        let comment_packet_index = packets
+            .iter()
+            .position(|packet| packet.data.starts_with(magic))
+            .ok_or_else(|| anyhow!("no comment header found in {:?}", path))?;
+
+        let (vendor, comments, trailing) = parse(&packets[comment_packet_index].data, magic)?;
+
+        Ok(Self {
+            magic,
+            vendor,
+            comments,
+            trailing,
+            packets,
+            comment_packet_index,
+        })
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(field))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set(&mut self, field: &str, value: String) {
+        match self
+            .comments
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case(field))
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.comments.push((field.to_ascii_uppercase(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, field: &str) -> Option<String> {
+        let index = self
+            .comments
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(field))?;
+        Some(self.comments.remove(index).1)
+    }
+
+    /// Re-encodes the comment packet from the current fields and rewrites
+    /// every packet of the stream to `path`, keeping each packet's original
+    /// serial/granule-position/page-boundary bookkeeping untouched.
+    pub fn write_to_path(&mut self, path: &Path) -> Result<()> {
+        self.packets[self.comment_packet_index].data =
+            serialize(self.magic, &self.vendor, &self.comments, &self.trailing);
+
+        let file = File::create(path)?;
+        let mut writer = PacketWriter::new(file);
+        let last_index = self.packets.len() - 1;
+
+        for (index, packet) in self.packets.iter().enumerate() {
+            let info = if index == last_index || packet.last_in_stream {
+                PacketWriteEndInfo::EndStream
+            } else if packet.last_in_page {
+                PacketWriteEndInfo::EndPage
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            writer.write_packet(
+                packet.data.clone(),
+                packet.stream_serial,
+                info,
+                packet.absgp_page,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_bytes<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8]> {
+    let end = pos
+        .checked_add(len)
+        .ok_or_else(|| anyhow!("comment header length overflow"))?;
+    let slice = data
+        .get(*pos..end)
+        .ok_or_else(|| anyhow!("truncated comment header"))?;
+    *pos = end;
+    Ok(slice)
+}
+
+fn read_u32(data: &[u8], pos: &mut usize) -> Result<u32> {
+    let bytes = read_bytes(data, pos, 4)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn parse(data: &[u8], magic: &[u8]) -> Result<(String, Vec<(String, String)>, Vec<u8>)> {
+    if !data.starts_with(magic) {
+        return Err(anyhow!("comment packet missing expected magic"));
+    }
+
+    let mut pos = magic.len();
+    let vendor_len = read_u32(data, &mut pos)? as usize;
+    let vendor = String::from_utf8(read_bytes(data, &mut pos, vendor_len)?.to_vec())?;
+
+    let count = read_u32(data, &mut pos)?;
+
+    // Each entry needs at least 4 bytes (its length prefix), so a count
+    // that couldn't possibly fit in what's left of the packet means a
+    // corrupted file, not a huge comment list -- reject it here rather
+    // than handing `Vec::with_capacity` a bogus size to allocate.
+    let remaining = data.len().saturating_sub(pos);
+    if count as usize > remaining / 4 {
+        return Err(anyhow!(
+            "comment header declares {count} entries, too many for {remaining} remaining bytes"
+        ));
+    }
+
+    let mut comments = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let len = read_u32(data, &mut pos)? as usize;
+        let entry = std::str::from_utf8(read_bytes(data, &mut pos, len)?)?;
+        if let Some((key, value)) = entry.split_once('=') {
+            comments.push((key.to_ascii_uppercase(), value.to_owned()));
+        }
+    }
+
+    let trailing = data[pos..].to_vec();
+    Ok((vendor, comments, trailing))
+}
+
+fn serialize(magic: &[u8], vendor: &str, comments: &[(String, String)], trailing: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(magic);
+    out.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    out.extend_from_slice(vendor.as_bytes());
+    out.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for (key, value) in comments {
+        let entry = format!("{key}={value}");
+        out.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+        out.extend_from_slice(entry.as_bytes());
+    }
+    out.extend_from_slice(trailing);
+    out
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use regex::Regex;
+use serde::Deserialize;
+
+fn default_rename_template() -> String {
+    "{tracknumber} - {title}.{ext}".to_string()
+}
+
+fn default_tracknumber_padding() -> usize {
+    2
+}
+
+fn default_illegal_characters() -> HashMap<char, String> {
+    [(':', " ".to_string()), ('/', " ".to_string())]
+        .into_iter()
+        .collect()
+}
+
+/// User-overridable rename template, padding width, illegal-character
+/// replacements and genre aliases, loaded from `--config` or the default
+/// config path. Missing keys fall back to the current hard-coded defaults.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    #[serde(default = "default_rename_template")]
+    pub rename_template: String,
+    #[serde(default = "default_tracknumber_padding")]
+    pub tracknumber_padding: usize,
+    #[serde(default = "default_illegal_characters")]
+    pub illegal_characters: HashMap<char, String>,
+    #[serde(default)]
+    pub genre_aliases: HashMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rename_template: default_rename_template(),
+            tracknumber_padding: default_tracknumber_padding(),
+            illegal_characters: default_illegal_characters(),
+            genre_aliases: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Resolves `genre` to its canonical spelling via `genre_aliases`,
+    /// matching case-insensitively, or returns it unchanged.
+    pub fn canonical_genre(&self, genre: &str) -> String {
+        self.genre_aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(genre))
+            .map(|(_, canonical)| canonical.to_owned())
+            .unwrap_or_else(|| genre.to_owned())
+    }
+
+    fn sanitize(&self, value: &str) -> String {
+        value
+            .chars()
+            .map(|c| {
+                self.illegal_characters
+                    .get(&c)
+                    .cloned()
+                    .unwrap_or_else(|| c.to_string())
+            })
+            .collect()
+    }
+
+    /// Renders `rename_template` against the given `{placeholder: value}`
+    /// fields, sanitizing each value against `illegal_characters` first.
+    ///
+    /// Substitutes every `{placeholder}` in a single pass over the template
+    /// instead of one `replace` per field: a field value that itself
+    /// contains literal `{other_placeholder}` text must not be mistaken for
+    /// a placeholder by a later substitution.
+    pub fn render_rename(&self, fields: &HashMap<&str, String>) -> String {
+        let re = Regex::new(r"\{(\w+)\}").expect("placeholder pattern is valid");
+        re.replace_all(&self.rename_template, |caps: &regex::Captures| {
+            fields
+                .get(&caps[1])
+                .map(|value| self.sanitize(value))
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_genre_resolves_case_insensitive_alias() {
+        let config = Config {
+            genre_aliases: [("hiphop".to_string(), "Hip-Hop".to_string())].into(),
+            ..Config::default()
+        };
+        assert_eq!(config.canonical_genre("HipHop"), "Hip-Hop");
+    }
+
+    #[test]
+    fn canonical_genre_passes_through_unknown_genres() {
+        let config = Config::default();
+        assert_eq!(config.canonical_genre("Shoegaze"), "Shoegaze");
+    }
+
+    #[test]
+    fn render_rename_substitutes_and_sanitizes_fields() {
+        let config = Config::default();
+        let fields = HashMap::from([
+            ("tracknumber", "07".to_string()),
+            ("title", "A/B: Side".to_string()),
+            ("ext", "flac".to_string()),
+        ]);
+        assert_eq!(config.render_rename(&fields), "07 - A B  Side.flac");
+    }
+
+    #[test]
+    fn render_rename_does_not_resubstitute_placeholder_text_from_field_values() {
+        let config = Config::default();
+        let fields = HashMap::from([
+            ("tracknumber", "07".to_string()),
+            ("title", "Live {title} Version".to_string()),
+            ("ext", "flac".to_string()),
+        ]);
+        assert_eq!(
+            config.render_rename(&fields),
+            "07 - Live {title} Version.flac"
+        );
+    }
+}
+
+fn default_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_default()
+        .join("audio-lint")
+        .join("config.toml")
+}
+
+/// Loads the config from `path`, or the default config path when `path` is
+/// `None`. A missing file is not an error: it just yields `Config::default()`.
+pub fn load(path: Option<&Path>) -> Result<Config> {
+    let path = path.map(Path::to_owned).unwrap_or_else(default_path);
+
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let contents = std::fs::read_to_string(&path)?;
+    Ok(toml::from_str(&contents)?)
+}
@@ -1,13 +1,31 @@
-use std::ffi;
-use std::path;
-use walkdir::WalkDir;
+use std::ffi::OsStr;
+use std::path::Path;
+
+use walkdir::{DirEntry, WalkDir};
 
 use crate::parser;
 
-pub fn load_files(args: parser::Args){
-    let mut entry_iter = WalkDir::new(path::Path::new(&args.path))
+const SUPPORTED_EXTENSIONS: &[&str] = &["flac", "mp3", "ogg", "opus"];
+
+fn is_supported(entry: &DirEntry) -> bool {
+    entry
+        .path()
+        .extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext))
+}
+
+/// True for entries backed by a FLAC file, the only format `--replaygain`
+/// and `--genhtml` currently know how to decode/read streaminfo from.
+pub fn is_flac(entry: &DirEntry) -> bool {
+    entry.path().extension() == Some(OsStr::new("flac"))
+}
+
+/// Walks `args.path` and yields every entry whose extension is one of the
+/// formats we have a `TagBackend` for.
+pub fn load_files(args: &parser::Args) -> impl Iterator<Item = DirEntry> {
+    WalkDir::new(Path::new(&args.path))
         .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension() == Some(&ffi::OsString::from("flac")))
-        .peekable()
+        .filter_map(|entry| entry.ok())
+        .filter(is_supported)
 }
@@ -1,31 +1,188 @@
+mod catalog;
+mod comment_header;
+mod config;
+mod file;
 mod parser;
 mod process;
+mod replaygain;
+mod tag;
 
 use crate::parser::Args;
 use crate::process::process_entry;
 use clap::Parser;
 use colored::Colorize;
-use std::ffi;
-use std::path::Path;
-use walkdir::WalkDir;
+use rayon::prelude::*;
+use spinner::SpinnerBuilder;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 
 fn main() {
     let args = Args::parse();
 
     println!("started...");
 
-    let messages = WalkDir::new(Path::new(&args.path))
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.path().extension() == Some(&ffi::OsString::from("flac")))
-        .map(|entry| match process_entry(&entry, &args) {
-            Ok(msg) => msg.join("\n"),
-            Err(err) => err.to_string().red().to_string(),
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
+    let config = config::load(args.config.as_deref().map(std::path::Path::new))
+        .expect("failed to load config");
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.unwrap_or(0))
+        .build()
+        .expect("failed to build worker pool");
+
+    let sp = Mutex::new(SpinnerBuilder::new("scanning...".into()).start());
+    let validation_failed = AtomicBool::new(false);
+
+    let entries: Vec<_> = file::load_files(&args).collect();
+
+    // `--genhtml` alone doesn't touch tags at all: `catalog::collect` reads
+    // them itself and tolerates unreadable ones, so running `process_entry`
+    // here would just open every file's tag a second time and surface
+    // unrelated read errors for files the catalog would have skipped anyway.
+    //
+    // `--replaygain` and the tag-editing modes (`--validate`, `--rename`,
+    // ...) are independent passes over the same file list, so both run
+    // when both are requested instead of one silently winning.
+    let mut results: Vec<(String, String)> = Vec::new();
+
+    if args.replaygain {
+        results.extend(pool.install(|| run_replaygain(&entries, &args, &sp)));
+    }
+
+    if args.has_tag_mode() {
+        // Messages are keyed by path and sorted afterwards so output stays
+        // stable regardless of which worker finishes first.
+        results.extend(pool.install(|| {
+            entries
+                .par_iter()
+                .map(|entry| {
+                    let path = entry.path().to_string_lossy().into_owned();
+                    let message = match process_entry(entry, &args, &sp, &validation_failed, &config)
+                    {
+                        Ok(msg) => msg.join("\n"),
+                        Err(err) => err.to_string().red().to_string(),
+                    };
+                    (path, message)
+                })
+                .collect::<Vec<(String, String)>>()
+        }));
+    }
+
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+
+    sp.into_inner().unwrap().close();
 
     println!("done!");
 
-    println!("{}", messages);
+    println!(
+        "{}",
+        results
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect::<Vec<String>>()
+            .join("\n")
+    );
+
+    if let Some(dest) = &args.genhtml {
+        let flac_entries: Vec<walkdir::DirEntry> = entries
+            .iter()
+            .filter(|entry| file::is_flac(entry))
+            .cloned()
+            .collect();
+        let albums = catalog::collect(&flac_entries);
+        match std::fs::write(dest, catalog::render_html(&albums)) {
+            Ok(()) => println!("genhtml: wrote catalog to {dest}"),
+            Err(err) => println!("{}", format!("genhtml: {err}").red()),
+        }
+    }
+
+    if args.validate && validation_failed.load(Ordering::Relaxed) {
+        std::process::exit(1);
+    }
+}
+
+/// Analyzes every FLAC entry's loudness in parallel, then groups tracks by
+/// parent directory (album) to derive and write REPLAYGAIN_ALBUM_* tags
+/// alongside each track's own REPLAYGAIN_TRACK_* values.
+fn run_replaygain(
+    entries: &[walkdir::DirEntry],
+    args: &Args,
+    sp: &Mutex<spinner::SpinnerHandle>,
+) -> Vec<(String, String)> {
+    let flac_entries: Vec<&walkdir::DirEntry> = entries
+        .iter()
+        .filter(|entry| file::is_flac(entry))
+        .collect();
+
+    let analyzed: Vec<(PathBuf, anyhow::Result<replaygain::TrackLoudness>)> = flac_entries
+        .par_iter()
+        .map(|entry| {
+            let path = entry.path();
+            sp.lock()
+                .unwrap()
+                .update(path.to_string_lossy().into_owned());
+
+            if !args.force_replaygain && replaygain::already_tagged(path).unwrap_or(false) {
+                return (path.to_owned(), Err(anyhow::anyhow!("already tagged, skipping")));
+            }
+
+            (path.to_owned(), replaygain::analyze_track(path))
+        })
+        .collect();
+
+    let mut by_album: HashMap<PathBuf, Vec<replaygain::TrackLoudness>> = HashMap::new();
+    let mut messages: Vec<(String, String)> = Vec::new();
+
+    for (path, result) in analyzed {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        match result {
+            Ok(track) => {
+                let parent = path.parent().unwrap_or(&path).to_owned();
+                by_album.entry(parent).or_default().push(track);
+            }
+            Err(err) => {
+                messages.push((
+                    path.to_string_lossy().into_owned(),
+                    format!("ReplayGain {}: {err}", file_name.red()),
+                ));
+            }
+        }
+    }
+
+    for tracks in by_album.into_values() {
+        let album = replaygain::album_loudness(&tracks).ok();
+
+        for track in tracks {
+            let file_name = track
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            let message = match track.integrated_lufs() {
+                Ok(lufs) => {
+                    let track_gain = replaygain::track_gain_db(lufs);
+                    match replaygain::write_tags(
+                        &track.path,
+                        track_gain,
+                        track.peak,
+                        album,
+                        args.run,
+                    ) {
+                        Ok(()) => {
+                            let gain = replaygain::format_gain(track_gain);
+                            let gain = if args.run { gain.green() } else { gain.yellow() };
+                            format!("ReplayGain: {} ({})", file_name.dimmed(), gain)
+                        }
+                        Err(err) => format!("ReplayGain {}: {err}", file_name.red()),
+                    }
+                }
+                Err(err) => format!("ReplayGain {}: {err}", file_name.red()),
+            };
+            messages.push((track.path.to_string_lossy().into_owned(), message));
+        }
+    }
+
+    messages
 }
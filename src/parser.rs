@@ -69,4 +69,71 @@ pub struct Args {
 
     #[arg(long = "year", help = "specify year")]
     pub year: Option<u32>,
+
+    #[arg(
+        long = "config",
+        help = "path to a TOML config overriding the rename template, padding, \
+                illegal-character rules and genre aliases"
+    )]
+    pub config: Option<String>,
+
+    #[arg(
+        short = 'j',
+        long = "jobs",
+        alias = "threads",
+        help = "number of worker threads to process files with (defaults to available parallelism)"
+    )]
+    pub jobs: Option<usize>,
+
+    #[arg(
+        long = "validate",
+        help = "check required tags without modifying anything; exits non-zero on failure",
+        group = "mode"
+    )]
+    pub validate: bool,
+
+    #[arg(
+        long = "ascii",
+        help = "transliterate TITLE/ARTIST/ALBUM (and rename targets) to ASCII",
+        group = "mode"
+    )]
+    pub ascii: bool,
+
+    #[arg(
+        long = "genhtml",
+        help = "write a browsable HTML catalog of the scanned library to <dest>",
+        group = "mode"
+    )]
+    pub genhtml: Option<String>,
+
+    #[arg(
+        long = "replaygain",
+        help = "measure EBU R128 loudness and write REPLAYGAIN_* tags",
+        group = "mode"
+    )]
+    pub replaygain: bool,
+
+    #[arg(
+        long = "force-replaygain",
+        help = "re-analyze and overwrite files that already carry REPLAYGAIN_* tags",
+        default_value_t = false
+    )]
+    pub force_replaygain: bool,
+}
+
+impl Args {
+    /// True if any flag that reads or writes tags through `process_entry`
+    /// is set, as opposed to modes like `--genhtml` or `--replaygain` that
+    /// read tags their own way.
+    pub fn has_tag_mode(&self) -> bool {
+        self.normalize_tracknumber
+            || self.normalize_title
+            || self.normalize_year
+            || self.rename
+            || self.erase
+            || self.set_genre
+            || self.set_year
+            || self.validate
+            || self.ascii
+    }
 }
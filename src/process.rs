@@ -1,23 +1,29 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+use crate::config::Config;
 use crate::parser;
+use crate::tag::{self, TagBackend};
 use anyhow::anyhow;
 use anyhow::Result;
 use colored::Colorize;
-use metaflac::block::VorbisComment;
 use regex::Regex;
 use spinner::SpinnerHandle;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use titlecase::titlecase;
 use unic_normal::StrNormalForm;
 use walkdir::DirEntry;
 
-const TRACKNUMBER: &str = "TRACKNUMBER";
-const TITLE: &str = "TITLE";
-const GENRE: &str = "GENRE";
-const YEAR: &str = "DATE";
-const COMMENT: &str = "COMMENT";
-const LYRICS: &str = "LYRICS";
+const REQUIRED_FIELDS: &[&str] = &[
+    tag::TITLE,
+    tag::TRACKNUMBER,
+    tag::DATE,
+    tag::GENRE,
+    tag::ARTIST,
+    tag::ALBUM,
+];
 
 struct BeforeAfter {
     old: String,
@@ -56,18 +62,17 @@ fn create_message(msg: Result<Change>, strategy: &str, file_name: &str, run: boo
 }
 
 fn edit_tag<S: Strategy>(
-    comments: &mut VorbisComment,
+    tag: &mut dyn TagBackend,
     field: &str,
     strategy: S,
 ) -> Result<Change, anyhow::Error> {
-    let old = comments
+    let old = tag
         .get(field)
-        .and_then(|comments| comments.get(0))
         .ok_or(anyhow!("failed load tag: {}", field))?;
 
-    let new = strategy.transform(old)?;
+    let new = strategy.transform(&old)?;
 
-    if strategy.changed(old, &new) {
+    if strategy.changed(&old, &new) {
         return Ok(Unchanged);
     }
 
@@ -76,15 +81,15 @@ fn edit_tag<S: Strategy>(
         new: new.to_owned(),
     };
 
-    comments.set(field, vec![new]);
+    tag.set(field, new);
 
     Ok(Changed(msg))
 }
 
-fn clear_tag(comments: &mut VorbisComment, field: &str) -> Result<Change, anyhow::Error> {
-    let res = match comments.comments.remove_entry(field) {
-        Some(_) => Cleared,
-        None => Unchanged,
+fn clear_tag(tag: &mut dyn TagBackend, field: &str) -> Result<Change, anyhow::Error> {
+    let res = match tag.remove(field) {
+        true => Cleared,
+        false => Unchanged,
     };
 
     Ok(res)
@@ -154,7 +159,54 @@ impl Strategy for SetYear {
     }
 }
 
-fn rename(path: &Path, comments: &mut VorbisComment, run: bool) -> Result<Change, anyhow::Error> {
+struct AsciiReduce;
+
+/// True for characters in the Combining Diacritical Marks block
+/// (U+0300..=U+036F) left over after NFKD decomposition, e.g. the combining
+/// tilde that "ñ" decomposes into ("n" + "\u{303}"). Combining marks from
+/// other blocks are not matched here.
+fn is_combining_mark(c: char) -> bool {
+    matches!(c as u32, 0x0300..=0x036F)
+}
+
+impl Strategy for AsciiReduce {
+    fn transform(&self, old: &str) -> Result<String, anyhow::Error> {
+        let decomposed: String = old.nfkd().filter(|c| !is_combining_mark(*c)).collect();
+        Ok(deunicode::deunicode(&decomposed))
+    }
+    fn changed(&self, old: &str, new: &str) -> bool {
+        old == new
+    }
+}
+
+#[cfg(test)]
+mod ascii_reduce_tests {
+    use super::*;
+
+    #[test]
+    fn is_combining_mark_matches_the_nfkd_tilde_range() {
+        assert!(is_combining_mark('\u{0303}'));
+        assert!(!is_combining_mark('n'));
+    }
+
+    #[test]
+    fn transform_strips_accents() {
+        assert_eq!(AsciiReduce.transform("Café").unwrap(), "Cafe");
+    }
+
+    #[test]
+    fn transform_reduces_non_latin_scripts_to_ascii() {
+        assert!(AsciiReduce.transform("日本語").unwrap().is_ascii());
+    }
+}
+
+fn rename(
+    path: &Path,
+    tag: &mut dyn TagBackend,
+    run: bool,
+    ascii: bool,
+    config: &Config,
+) -> Result<Change, anyhow::Error> {
     let old_name = path
         .file_name()
         .and_then(|name| name.to_str())
@@ -167,21 +219,39 @@ fn rename(path: &Path, comments: &mut VorbisComment, run: bool) -> Result<Change
         .parent()
         .ok_or(anyhow!("can't find parent path for {:?}", path))?;
 
-    let tracknumber = comments
-        .get(TRACKNUMBER)
-        .and_then(|field| field.get(0))
+    let tracknumber = tag
+        .get(tag::TRACKNUMBER)
         .ok_or(anyhow!("can't load tracknumber for {:?}", path))?;
-    let title = comments
-        .get(TITLE)
-        .and_then(|field| field.get(0))
+    let title = tag
+        .get(tag::TITLE)
         .ok_or(anyhow!("can't load title for {:?}", path))?;
+    let artist = tag.get(tag::ARTIST).unwrap_or_default();
+    let album = tag.get(tag::ALBUM).unwrap_or_default();
+    let year = tag.get(tag::DATE).unwrap_or_default();
+
+    let (title, artist, album, year) = if ascii {
+        (
+            AsciiReduce.transform(&title)?,
+            AsciiReduce.transform(&artist)?,
+            AsciiReduce.transform(&album)?,
+            AsciiReduce.transform(&year)?,
+        )
+    } else {
+        (title, artist, album, year)
+    };
 
-    let new_name = format!(
-        "{:0>2} - {}.{}",
-        tracknumber,
-        title.replace([':', '/'], " "),
-        ext
-    );
+    let fields = HashMap::from([
+        (
+            "tracknumber",
+            format!("{:0>width$}", tracknumber, width = config.tracknumber_padding),
+        ),
+        ("title", title),
+        ("artist", artist),
+        ("album", album),
+        ("year", year),
+        ("ext", ext.to_owned()),
+    ]);
+    let new_name = config.render_rename(&fields);
 
     if old_name.nfd().eq(new_name.nfd()) {
         return Ok(Unchanged);
@@ -200,10 +270,184 @@ fn rename(path: &Path, comments: &mut VorbisComment, run: bool) -> Result<Change
     Ok(Changed(result))
 }
 
+/// Checks a field against its `Strategy` and records a soft warning if the
+/// strategy would normalize it further, without touching the tag.
+fn soft_warn<S: Strategy>(old: &str, strategy: S, label: &str, warnings: &mut Vec<String>) {
+    if let Ok(new) = strategy.transform(old) {
+        if !strategy.changed(old, &new) {
+            warnings.push(format!("{label} would be normalized: {old:?} -> {new:?}"));
+        }
+    }
+}
+
+/// Checks required fields and format rules against `tag`, returning hard
+/// failures (missing/malformed fields) separately from soft warnings
+/// (fields that `--validate` didn't touch but a norm-* mode would change).
+fn validate(tag: &dyn TagBackend) -> (Vec<String>, Vec<String>) {
+    let mut failures = Vec::new();
+    let mut warnings = Vec::new();
+
+    for field in REQUIRED_FIELDS {
+        match tag.get(field) {
+            None => failures.push(format!("missing {field}")),
+            Some(value) if value.trim().is_empty() => failures.push(format!("{field} is empty")),
+            Some(_) => {}
+        }
+    }
+
+    if let Some(tracknumber) = tag.get(tag::TRACKNUMBER) {
+        if tracknumber.parse::<u32>().is_err() {
+            failures.push(format!("TRACKNUMBER is not an integer: {tracknumber:?}"));
+        } else {
+            soft_warn(&tracknumber, FormatNumber, "TRACKNUMBER", &mut warnings);
+        }
+    }
+
+    if let Some(date) = tag.get(tag::DATE) {
+        match Regex::new(r"\d{4}") {
+            Ok(re) if re.is_match(&date) => soft_warn(&date, FormatYear, "DATE", &mut warnings),
+            _ => failures.push(format!("DATE has no four-digit year: {date:?}")),
+        }
+    }
+
+    if let Some(title) = tag.get(tag::TITLE) {
+        soft_warn(&title, FormatText, "TITLE", &mut warnings);
+    }
+
+    (failures, warnings)
+}
+
+fn create_validation_message(file_name: &str, failures: &[String], warnings: &[String]) -> String {
+    if failures.is_empty() && warnings.is_empty() {
+        return format!("Validate: {}", file_name.dimmed());
+    }
+
+    let mut lines = vec![format!(
+        "Validate: {}",
+        if failures.is_empty() {
+            file_name.yellow()
+        } else {
+            file_name.red()
+        }
+    )];
+    lines.extend(failures.iter().map(|failure| format!("  ✗ {failure}")));
+    lines.extend(warnings.iter().map(|warning| format!("  ! {warning}")));
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod validate_tests {
+    use super::*;
+
+    struct FakeTag(HashMap<String, String>);
+
+    impl TagBackend for FakeTag {
+        fn get(&self, field: &str) -> Option<String> {
+            self.0.get(field).cloned()
+        }
+        fn set(&mut self, field: &str, value: String) {
+            self.0.insert(field.to_owned(), value);
+        }
+        fn remove(&mut self, field: &str) -> bool {
+            self.0.remove(field).is_some()
+        }
+        fn save(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn complete_tag() -> FakeTag {
+        FakeTag(HashMap::from([
+            (tag::TITLE.to_string(), "A Song".to_string()),
+            (tag::TRACKNUMBER.to_string(), "5".to_string()),
+            (tag::DATE.to_string(), "1999".to_string()),
+            (tag::GENRE.to_string(), "Rock".to_string()),
+            (tag::ARTIST.to_string(), "a band".to_string()),
+            (tag::ALBUM.to_string(), "an album".to_string()),
+        ]))
+    }
+
+    #[test]
+    fn validate_passes_a_complete_tag() {
+        let (failures, warnings) = validate(&complete_tag());
+        assert!(failures.is_empty());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn validate_reports_missing_required_field() {
+        let mut tag = complete_tag();
+        tag.remove(tag::GENRE);
+        let (failures, _) = validate(&tag);
+        assert_eq!(failures, vec!["missing GENRE".to_string()]);
+    }
+
+    #[test]
+    fn validate_reports_blank_required_field() {
+        let mut tag = complete_tag();
+        tag.set(tag::ARTIST, "   ".to_string());
+        let (failures, _) = validate(&tag);
+        assert_eq!(failures, vec!["ARTIST is empty".to_string()]);
+    }
+
+    #[test]
+    fn validate_reports_non_numeric_tracknumber() {
+        let mut tag = complete_tag();
+        tag.set(tag::TRACKNUMBER, "5/12".to_string());
+        let (failures, _) = validate(&tag);
+        assert_eq!(
+            failures,
+            vec!["TRACKNUMBER is not an integer: \"5/12\"".to_string()]
+        );
+    }
+
+    #[test]
+    fn validate_reports_date_without_four_digit_year() {
+        let mut tag = complete_tag();
+        tag.set(tag::DATE, "99".to_string());
+        let (failures, _) = validate(&tag);
+        assert_eq!(
+            failures,
+            vec!["DATE has no four-digit year: \"99\"".to_string()]
+        );
+    }
+
+    #[test]
+    fn validate_warns_without_failing_when_a_norm_mode_would_still_change_the_tag() {
+        let mut tag = complete_tag();
+        tag.set(tag::TRACKNUMBER, "05".to_string());
+        let (failures, warnings) = validate(&tag);
+        assert!(failures.is_empty());
+        assert_eq!(
+            warnings,
+            vec!["TRACKNUMBER would be normalized: \"05\" -> \"5\"".to_string()]
+        );
+    }
+
+    #[test]
+    fn create_validation_message_is_a_single_line_when_clean() {
+        let message = create_validation_message("song.flac", &[], &[]);
+        assert_eq!(message.lines().count(), 1);
+        assert!(message.contains("song.flac"));
+    }
+
+    #[test]
+    fn create_validation_message_lists_failures_and_warnings() {
+        let failures = vec!["missing GENRE".to_string()];
+        let warnings = vec!["TITLE would be normalized: \"a\" -> \"A\"".to_string()];
+        let message = create_validation_message("song.flac", &failures, &warnings);
+        assert!(message.contains("✗ missing GENRE"));
+        assert!(message.contains("! TITLE would be normalized: \"a\" -> \"A\""));
+    }
+}
+
 pub fn process_entry(
     entry: &DirEntry,
     args: &parser::Args,
-    sp: &SpinnerHandle,
+    sp: &Mutex<SpinnerHandle>,
+    validation_failed: &AtomicBool,
+    config: &Config,
 ) -> Result<Vec<String>, anyhow::Error> {
     let run = args.run;
 
@@ -213,7 +457,7 @@ pub fn process_entry(
         .and_then(|name| name.to_str())
         .ok_or(anyhow!("can't load file {:?}", entry))?;
 
-    sp.update(
+    sp.lock().unwrap().update(
         path.to_str()
             .ok_or(anyhow!("couldn't convert path"))?
             .to_string(),
@@ -221,56 +465,83 @@ pub fn process_entry(
 
     let mut messages: Vec<String> = Vec::new();
 
-    let mut tag = metaflac::Tag::read_from_path(path)?;
-    let comments = tag.vorbis_comments_mut();
+    let mut tag = tag::open(path)?;
+    let tag = tag.as_mut();
 
     let mut tag_modified = false;
 
     if args.normalize_tracknumber {
-        let msg = edit_tag(comments, TRACKNUMBER, FormatNumber);
+        let msg = edit_tag(tag, tag::TRACKNUMBER, FormatNumber);
         if msg.is_ok() {
             tag_modified = true
         };
         messages.push(create_message(msg, "Norm. Numb.", file_name, run));
     }
     if args.normalize_title {
-        let msg = edit_tag(comments, TITLE, FormatText);
+        let msg = edit_tag(tag, tag::TITLE, FormatText);
         if msg.is_ok() {
             tag_modified = true
         };
         messages.push(create_message(msg, "Norm. Title", file_name, run));
     }
     if args.normalize_year {
-        let msg = edit_tag(comments, YEAR, FormatYear);
+        let msg = edit_tag(tag, tag::DATE, FormatYear);
         if msg.is_ok() {
             tag_modified = true
         };
         messages.push(create_message(msg, "Norm. Year", file_name, run));
     }
-    if let Some(genre) = &args.set_genre {
-        let genre = genre.to_owned();
-        let msg = edit_tag(comments, GENRE, SetGenre { genre });
+    if args.set_genre {
+        let genre = args
+            .genre
+            .clone()
+            .ok_or(anyhow!("--set-genre requires --genre"))?;
+        let genre = config.canonical_genre(&genre);
+        let msg = edit_tag(tag, tag::GENRE, SetGenre { genre });
         if msg.is_ok() {
             tag_modified = true
         };
         messages.push(create_message(msg, "Set Genre", file_name, run));
     }
-    if let Some(year) = args.set_year {
-        let msg = edit_tag(comments, YEAR, SetYear { year });
+    if args.set_year {
+        let year = args
+            .year
+            .ok_or(anyhow!("--set-year requires --year"))?;
+        let msg = edit_tag(tag, tag::DATE, SetYear { year });
         if msg.is_ok() {
             tag_modified = true
         };
         messages.push(create_message(msg, "Set Year", file_name, run));
     }
 
+    if args.ascii {
+        let title_msg = edit_tag(tag, tag::TITLE, AsciiReduce);
+        let artist_msg = edit_tag(tag, tag::ARTIST, AsciiReduce);
+        let album_msg = edit_tag(tag, tag::ALBUM, AsciiReduce);
+        if title_msg.is_ok() || artist_msg.is_ok() || album_msg.is_ok() {
+            tag_modified = true;
+        }
+        messages.push(create_message(title_msg, "ASCII Title", file_name, run));
+        messages.push(create_message(artist_msg, "ASCII Artist", file_name, run));
+        messages.push(create_message(album_msg, "ASCII Album", file_name, run));
+    }
+
+    if args.validate {
+        let (failures, warnings) = validate(tag);
+        if !failures.is_empty() {
+            validation_failed.store(true, Ordering::Relaxed);
+        }
+        messages.push(create_validation_message(file_name, &failures, &warnings));
+    }
+
     // Special modes
     if args.rename {
-        let msg = rename(path, comments, run);
+        let msg = rename(path, tag, run, args.ascii, config);
         messages.push(create_message(msg, "Rename", file_name, run));
     }
     if args.erase {
-        let comment_msg = clear_tag(comments, COMMENT);
-        let lyrics_msg = clear_tag(comments, LYRICS);
+        let comment_msg = clear_tag(tag, tag::COMMENT);
+        let lyrics_msg = clear_tag(tag, tag::LYRICS);
 
         if comment_msg.is_ok() || lyrics_msg.is_ok() {
             tag_modified = true;
@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use ebur128::{EbuR128, Mode};
+
+use crate::tag;
+
+/// Per-track loudness measurement, kept around so album values can be
+/// derived by combining states instead of re-decoding every track.
+pub struct TrackLoudness {
+    pub path: PathBuf,
+    meter: EbuR128,
+    pub peak: f64,
+}
+
+impl TrackLoudness {
+    pub fn integrated_lufs(&self) -> Result<f64> {
+        Ok(self.meter.loudness_global()?)
+    }
+}
+
+/// Decodes a FLAC file and feeds it through an EBU R128 loudness meter,
+/// tracking the maximum absolute sample amplitude (the sample peak, in the
+/// 0.0-1.0 range) as the track peak.
+pub fn analyze_track(path: &Path) -> Result<TrackLoudness> {
+    let mut reader = claxon::FlacReader::open(path)?;
+    let info = reader.streaminfo();
+
+    let mut meter = EbuR128::new(info.channels, info.sample_rate, Mode::I | Mode::SAMPLE_PEAK)?;
+    let max_amplitude = (1i64 << (info.bits_per_sample - 1)) as f64;
+    let mut peak: f64 = 0.0;
+
+    let mut frame = Vec::with_capacity(info.channels as usize);
+    for sample in reader.samples() {
+        frame.push(sample?);
+        if frame.len() == info.channels as usize {
+            meter.add_frames_i32(&frame)?;
+            peak = frame.iter().fold(peak, |peak, &s| {
+                peak.max((s as f64 / max_amplitude).abs())
+            });
+            frame.clear();
+        }
+    }
+
+    Ok(TrackLoudness {
+        path: path.to_owned(),
+        meter,
+        peak,
+    })
+}
+
+/// Combines already-measured tracks into REPLAYGAIN_ALBUM_* values for the
+/// shared program, per the EBU R128 multi-stream algorithm. Returns
+/// `(album gain in dB, album peak)`, the shape `write_tags` expects.
+pub fn album_loudness(tracks: &[TrackLoudness]) -> Result<(f64, f64)> {
+    let meters: Vec<&EbuR128> = tracks.iter().map(|t| &t.meter).collect();
+    let lufs = EbuR128::loudness_global_multiple(meters.into_iter())?;
+    let peak = tracks.iter().fold(0.0_f64, |peak, t| peak.max(t.peak));
+    Ok((track_gain_db(lufs), peak))
+}
+
+pub fn track_gain_db(lufs: f64) -> f64 {
+    -18.0 - lufs
+}
+
+pub fn format_gain(gain_db: f64) -> String {
+    format!("{gain_db:.2} dB")
+}
+
+pub fn format_peak(peak: f64) -> String {
+    format!("{peak:.6}")
+}
+
+/// True if the file already carries a REPLAYGAIN_TRACK_GAIN tag, so
+/// `--replaygain` can skip it unless `--force-replaygain` is given.
+pub fn already_tagged(path: &Path) -> Result<bool> {
+    Ok(tag::open(path)?.get(tag::REPLAYGAIN_TRACK_GAIN).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_gain_targets_minus_18_lufs() {
+        assert_eq!(track_gain_db(-18.0), 0.0);
+        assert_eq!(track_gain_db(-23.0), 5.0);
+        assert_eq!(track_gain_db(-10.0), -8.0);
+    }
+
+    #[test]
+    fn format_gain_has_two_decimals_and_unit() {
+        assert_eq!(format_gain(5.0), "5.00 dB");
+        assert_eq!(format_gain(-1.5), "-1.50 dB");
+    }
+
+    #[test]
+    fn format_peak_has_six_decimals() {
+        assert_eq!(format_peak(0.5), "0.500000");
+    }
+}
+
+pub fn write_tags(
+    path: &Path,
+    track_gain_db: f64,
+    track_peak: f64,
+    album: Option<(f64, f64)>,
+    run: bool,
+) -> Result<()> {
+    let mut backend = tag::open(path)?;
+
+    backend.set(tag::REPLAYGAIN_TRACK_GAIN, format_gain(track_gain_db));
+    backend.set(tag::REPLAYGAIN_TRACK_PEAK, format_peak(track_peak));
+
+    if let Some((album_gain_db, album_peak)) = album {
+        backend.set(tag::REPLAYGAIN_ALBUM_GAIN, format_gain(album_gain_db));
+        backend.set(tag::REPLAYGAIN_ALBUM_PEAK, format_peak(album_peak));
+    }
+
+    if run {
+        backend.save()?;
+    }
+
+    Ok(())
+}
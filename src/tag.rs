@@ -0,0 +1,275 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use id3::TagLike;
+
+use crate::comment_header::CommentHeader;
+
+const VORBIS_COMMENT_MAGIC: &[u8] = b"\x03vorbis";
+const OPUS_TAGS_MAGIC: &[u8] = b"OpusTags";
+
+pub const TITLE: &str = "TITLE";
+pub const TRACKNUMBER: &str = "TRACKNUMBER";
+pub const DATE: &str = "DATE";
+pub const GENRE: &str = "GENRE";
+pub const ARTIST: &str = "ARTIST";
+pub const ALBUM: &str = "ALBUM";
+pub const COMMENT: &str = "COMMENT";
+pub const LYRICS: &str = "LYRICS";
+
+pub const REPLAYGAIN_TRACK_GAIN: &str = "REPLAYGAIN_TRACK_GAIN";
+pub const REPLAYGAIN_TRACK_PEAK: &str = "REPLAYGAIN_TRACK_PEAK";
+pub const REPLAYGAIN_ALBUM_GAIN: &str = "REPLAYGAIN_ALBUM_GAIN";
+pub const REPLAYGAIN_ALBUM_PEAK: &str = "REPLAYGAIN_ALBUM_PEAK";
+
+/// Uniform access to a single file's tags, regardless of container format.
+///
+/// `process_entry` runs the existing `Strategy` transforms against field
+/// names normalized to the Vorbis comment spelling (TITLE, TRACKNUMBER, ...);
+/// each backend maps those onto whatever its own format actually stores.
+pub trait TagBackend {
+    fn get(&self, field: &str) -> Option<String>;
+    fn set(&mut self, field: &str, value: String);
+    fn remove(&mut self, field: &str) -> bool;
+    fn save(&mut self) -> Result<()>;
+}
+
+/// Opens the right backend for `path` based on its extension.
+pub fn open(path: &Path) -> Result<Box<dyn TagBackend>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("flac") => Ok(Box::new(FlacBackend::open(path)?)),
+        Some("mp3") => Ok(Box::new(Mp3Backend::open(path)?)),
+        Some("ogg") => Ok(Box::new(OggBackend::open(path)?)),
+        Some("opus") => Ok(Box::new(OpusBackend::open(path)?)),
+        other => Err(anyhow!("unsupported file extension: {:?}", other)),
+    }
+}
+
+pub struct FlacBackend {
+    path: PathBuf,
+    tag: metaflac::Tag,
+}
+
+impl FlacBackend {
+    fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_owned(),
+            tag: metaflac::Tag::read_from_path(path)?,
+        })
+    }
+
+    fn comments(&mut self) -> &mut metaflac::block::VorbisComment {
+        self.tag.vorbis_comments_mut()
+    }
+}
+
+impl TagBackend for FlacBackend {
+    fn get(&self, field: &str) -> Option<String> {
+        self.tag
+            .vorbis_comments()
+            .and_then(|comments| comments.get(field))
+            .and_then(|values| values.first())
+            .cloned()
+    }
+
+    fn set(&mut self, field: &str, value: String) {
+        self.comments().set(field, vec![value]);
+    }
+
+    fn remove(&mut self, field: &str) -> bool {
+        self.comments().comments.remove_entry(field).is_some()
+    }
+
+    fn save(&mut self) -> Result<()> {
+        self.tag.write_to_path(&self.path)?;
+        Ok(())
+    }
+}
+
+/// Maps the normalized Vorbis-style field names onto ID3v2 frame ids.
+fn id3_frame(field: &str) -> Option<&'static str> {
+    match field {
+        TITLE => Some("TIT2"),
+        TRACKNUMBER => Some("TRCK"),
+        DATE => Some("TDRC"),
+        GENRE => Some("TCON"),
+        ARTIST => Some("TPE1"),
+        ALBUM => Some("TALB"),
+        COMMENT => Some("COMM"),
+        LYRICS => Some("USLT"),
+        _ => None,
+    }
+}
+
+pub struct Mp3Backend {
+    path: PathBuf,
+    tag: id3::Tag,
+}
+
+impl Mp3Backend {
+    fn open(path: &Path) -> Result<Self> {
+        let tag = match id3::Tag::read_from_path(path) {
+            Ok(tag) => tag,
+            Err(id3::Error {
+                kind: id3::ErrorKind::NoTag,
+                ..
+            }) => id3::Tag::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            path: path.to_owned(),
+            tag,
+        })
+    }
+}
+
+impl TagBackend for Mp3Backend {
+    fn get(&self, field: &str) -> Option<String> {
+        let frame_id = id3_frame(field)?;
+        let text = self
+            .tag
+            .get(frame_id)
+            .and_then(|frame| frame.content().text())?;
+
+        // TRCK commonly stores "track/total" (e.g. "5/12"); the rest of the
+        // program only ever wants the bare track number.
+        if field == TRACKNUMBER {
+            Some(text.split('/').next().unwrap_or(text).to_owned())
+        } else {
+            Some(text.to_owned())
+        }
+    }
+
+    fn set(&mut self, field: &str, value: String) {
+        let Some(frame_id) = id3_frame(field) else {
+            return;
+        };
+        self.tag.set_text(frame_id, value);
+    }
+
+    fn remove(&mut self, field: &str) -> bool {
+        let Some(frame_id) = id3_frame(field) else {
+            return false;
+        };
+        let existed = self.tag.get(frame_id).is_some();
+        self.tag.remove(frame_id);
+        existed
+    }
+
+    fn save(&mut self) -> Result<()> {
+        self.tag
+            .write_to_path(&self.path, id3::Version::Id3v24)?;
+        Ok(())
+    }
+}
+
+/// Reads the "\x03vorbis"-tagged comment packet carried by Ogg Vorbis
+/// streams. Not used for `.opus`, whose comment packet starts with
+/// "OpusTags" instead -- see [`OpusBackend`].
+pub struct OggBackend {
+    path: PathBuf,
+    comments: CommentHeader,
+}
+
+impl OggBackend {
+    fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_owned(),
+            comments: CommentHeader::read_from_path(path, VORBIS_COMMENT_MAGIC)?,
+        })
+    }
+}
+
+impl TagBackend for OggBackend {
+    fn get(&self, field: &str) -> Option<String> {
+        self.comments.get(field).map(str::to_owned)
+    }
+
+    fn set(&mut self, field: &str, value: String) {
+        self.comments.set(field, value);
+    }
+
+    fn remove(&mut self, field: &str) -> bool {
+        self.comments.remove(field).is_some()
+    }
+
+    fn save(&mut self) -> Result<()> {
+        self.comments.write_to_path(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod id3_mapping_tests {
+    use super::*;
+
+    #[test]
+    fn id3_frame_maps_known_fields() {
+        assert_eq!(id3_frame(TRACKNUMBER), Some("TRCK"));
+        assert_eq!(id3_frame(ARTIST), Some("TPE1"));
+    }
+
+    #[test]
+    fn id3_frame_rejects_unknown_fields() {
+        assert_eq!(id3_frame(REPLAYGAIN_TRACK_GAIN), None);
+    }
+
+    #[test]
+    fn mp3_backend_strips_total_count_from_tracknumber() {
+        let mut tag = id3::Tag::new();
+        tag.set_text("TRCK", "5/12");
+        let backend = Mp3Backend {
+            path: PathBuf::from("test.mp3"),
+            tag,
+        };
+        assert_eq!(backend.get(TRACKNUMBER), Some("5".to_string()));
+    }
+
+    #[test]
+    fn mp3_backend_passes_through_bare_tracknumber() {
+        let mut tag = id3::Tag::new();
+        tag.set_text("TRCK", "5");
+        let backend = Mp3Backend {
+            path: PathBuf::from("test.mp3"),
+            tag,
+        };
+        assert_eq!(backend.get(TRACKNUMBER), Some("5".to_string()));
+    }
+}
+
+/// Reads the "OpusTags" comment packet carried by Ogg Opus streams.
+///
+/// Opus tags reuse the Vorbis comment field layout (vendor string plus a
+/// list of `KEY=value` entries) behind their own "OpusTags" magic, so this
+/// shares `OggBackend`'s `CommentHeader` parser and only passes a
+/// different magic.
+pub struct OpusBackend {
+    path: PathBuf,
+    comments: CommentHeader,
+}
+
+impl OpusBackend {
+    fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_owned(),
+            comments: CommentHeader::read_from_path(path, OPUS_TAGS_MAGIC)?,
+        })
+    }
+}
+
+impl TagBackend for OpusBackend {
+    fn get(&self, field: &str) -> Option<String> {
+        self.comments.get(field).map(str::to_owned)
+    }
+
+    fn set(&mut self, field: &str, value: String) {
+        self.comments.set(field, value);
+    }
+
+    fn remove(&mut self, field: &str) -> bool {
+        self.comments.remove(field).is_some()
+    }
+
+    fn save(&mut self) -> Result<()> {
+        self.comments.write_to_path(&self.path)
+    }
+}